@@ -1,18 +1,26 @@
+use pyo3::exceptions::PyValueError;
+use pyo3::PyErr;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
 pub enum DatasetError {
     #[error("CSV parsing error: {0}")]
     CsvParseError(String),
-    
+
     #[error("Arrow error: {0}")]
     ArrowError(String),
-    
+
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
-    
+
     #[error("Unknown error: {0}")]
     Unknown(String),
 }
 
-pub type Result<T> = std::result::Result<T, DatasetError>; 
\ No newline at end of file
+pub type Result<T> = std::result::Result<T, DatasetError>;
+
+impl From<DatasetError> for PyErr {
+    fn from(err: DatasetError) -> PyErr {
+        PyValueError::new_err(err.to_string())
+    }
+} 
\ No newline at end of file
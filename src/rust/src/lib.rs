@@ -8,37 +8,79 @@ mod simd_parser;
 mod stream_processor;
 mod metrics;
 mod error;
+mod schema;
 
+use pyo3::types::PyList;
 use simd_parser::SimdParser;
 use stream_processor::{StreamProcessor, ProcessingStats};
 use metrics::MetricsCollector;
+use schema::{ColumnDef, TickSchema};
 
-/// Parse CSV file using SIMD-optimized parser
+#[global_allocator]
+static GLOBAL_ALLOCATOR: metrics::TrackingAllocator = metrics::TrackingAllocator;
+
+/// Parse CSV file using SIMD-optimized parser, materializing it alongside
+/// as an Arrow IPC file (`<file_path>.arrow`) against the `ticks_v1` schema.
 #[pyfunction]
 fn parse_csv_simd(file_path: &str, batch_size: usize) -> PyResult<PyObject> {
-    let start_time = Instant::now();
-    
     // Check if file exists
     if !Path::new(file_path).exists() {
         return Err(PyErr::new::<pyo3::exceptions::PyFileNotFoundError, _>(
             format!("File not found: {}", file_path)
         ));
     }
-    
+
     // Create parser and process file
     let parser = SimdParser::new();
-    let result = parser.parse_csv_py(file_path, batch_size);
-    
+    let arrow_path = format!("{}.arrow", file_path);
+    let result = parser.parse_csv_py(file_path, &arrow_path, "ticks_v1", batch_size);
+
     // Return the result directly from the parser
     result
 }
 
-/// Process data stream with high performance
+/// Process data stream with high performance.
+///
+/// `source_url` is dispatched on its scheme (`file://`, `s3://`,
+/// `kafka://`/`tcp://`). If `on_batch` is given, it's called with a stats
+/// dict after every batch completes (that batch's `rows_processed`,
+/// `bytes_processed`, `throughput_mbps` and `processing_time_ms`; no
+/// rows-per-second figure), not just once at
+/// the end — the only way to see progress on a long-running `kafka://`/
+/// `tcp://` ingest.
 #[pyfunction]
-fn process_stream(source_url: &str, batch_size: usize, schema_id: &str) -> PyResult<PyObject> {
+#[pyo3(signature = (source_url, batch_size, schema_id, on_batch=None))]
+fn process_stream(
+    source_url: &str,
+    batch_size: usize,
+    schema_id: &str,
+    on_batch: Option<PyObject>,
+) -> PyResult<PyObject> {
     let processor = StreamProcessor::new();
-    let result = processor.process_stream(source_url, batch_size, schema_id);
-    
+
+    let mut callback = on_batch.map(|cb| {
+        move |stats: &ProcessingStats| {
+            let outcome = Python::with_gil(|py| -> PyResult<()> {
+                let batch_dict = PyDict::new(py);
+                batch_dict.set_item("rows_processed", stats.rows_processed)?;
+                batch_dict.set_item("bytes_processed", stats.bytes_processed)?;
+                batch_dict.set_item("throughput_mbps", stats.throughput_mbps)?;
+                batch_dict.set_item("processing_time_ms", stats.processing_time_ms)?;
+                cb.call1(py, (batch_dict,))?;
+                Ok(())
+            });
+            if let Err(e) = outcome {
+                eprintln!("process_stream: on_batch callback failed: {e}");
+            }
+        }
+    });
+    let on_batch_ref: Option<&mut dyn FnMut(&ProcessingStats)> = match &mut callback {
+        Some(cb) => Some(cb),
+        None => None,
+    };
+
+    let result = processor.process_stream(source_url, batch_size, schema_id, on_batch_ref);
+
     Python::with_gil(|py| {
         let result_dict = PyDict::new(py);
         match result {
@@ -58,6 +100,114 @@ fn process_stream(source_url: &str, batch_size: usize, schema_id: &str) -> PyRes
     })
 }
 
+/// Slice the rows of a time-sorted tick file whose timestamp falls in
+/// `[start_ts, end_ts]` (microseconds since the Unix epoch), without a full
+/// scan.
+#[pyfunction]
+fn query_range(path: &str, start_ts: i64, end_ts: i64) -> PyResult<PyObject> {
+    if !Path::new(path).exists() {
+        return Err(PyErr::new::<pyo3::exceptions::PyFileNotFoundError, _>(
+            format!("File not found: {}", path)
+        ));
+    }
+
+    let processor = StreamProcessor::new();
+    let result = processor.query_range(path, start_ts, end_ts);
+
+    Python::with_gil(|py| {
+        let result_dict = PyDict::new(py);
+        match result {
+            Ok(slice) => {
+                let rows = slice.iter().filter(|&&b| b == b'\n').count();
+                result_dict.set_item("status", "success")?;
+                result_dict.set_item("rows_processed", rows)?;
+                result_dict.set_item("bytes_processed", slice.len())?;
+                result_dict.set_item("data", pyo3::types::PyBytes::new(py, &slice))?;
+            }
+            Err(e) => {
+                result_dict.set_item("status", "error")?;
+                result_dict.set_item("error", e.to_string())?;
+            }
+        }
+        Ok(result_dict.into())
+    })
+}
+
+/// Declare a tick schema so `process_stream`/`csv_to_arrow` can validate
+/// incoming CSV headers and type columns accordingly instead of inferring
+/// them ad hoc. `columns` is `(name, data_type, nullable)`, in header order;
+/// `data_type` is one of `timestamp_us`, `utf8`, `float64`, `int64`.
+#[pyfunction]
+fn register_schema(
+    schema_id: &str,
+    columns: Vec<(String, String, bool)>,
+    ts_column: &str,
+) -> PyResult<PyObject> {
+    let result = (|| -> crate::error::Result<()> {
+        let columns = columns
+            .into_iter()
+            .map(|(name, type_name, nullable)| {
+                Ok(ColumnDef {
+                    data_type: schema::parse_data_type(&type_name)?,
+                    name,
+                    nullable,
+                })
+            })
+            .collect::<crate::error::Result<Vec<_>>>()?;
+        schema::register_schema(TickSchema {
+            schema_id: schema_id.to_string(),
+            columns,
+            ts_column: ts_column.to_string(),
+        })
+    })();
+
+    Python::with_gil(|py| {
+        let result_dict = PyDict::new(py);
+        match result {
+            Ok(()) => {
+                result_dict.set_item("status", "success")?;
+                result_dict.set_item("schema_id", schema_id)?;
+            }
+            Err(e) => {
+                result_dict.set_item("status", "error")?;
+                result_dict.set_item("error", e.to_string())?;
+            }
+        }
+        Ok(result_dict.into())
+    })
+}
+
+/// Look up a previously registered schema by id.
+#[pyfunction]
+fn get_schema(schema_id: &str) -> PyResult<PyObject> {
+    let result = schema::get_schema(schema_id);
+
+    Python::with_gil(|py| {
+        let result_dict = PyDict::new(py);
+        match result {
+            Ok(schema) => {
+                result_dict.set_item("status", "success")?;
+                result_dict.set_item("schema_id", schema.schema_id)?;
+                result_dict.set_item("ts_column", schema.ts_column)?;
+                let columns = PyList::empty(py);
+                for column in schema.columns {
+                    let column_dict = PyDict::new(py);
+                    column_dict.set_item("name", column.name)?;
+                    column_dict.set_item("data_type", format!("{:?}", column.data_type))?;
+                    column_dict.set_item("nullable", column.nullable)?;
+                    columns.append(column_dict)?;
+                }
+                result_dict.set_item("columns", columns)?;
+            }
+            Err(e) => {
+                result_dict.set_item("status", "error")?;
+                result_dict.set_item("error", e.to_string())?;
+            }
+        }
+        Ok(result_dict.into())
+    })
+}
+
 /// Get system metrics
 #[pyfunction]
 fn get_metrics() -> PyResult<PyObject> {
@@ -84,34 +234,54 @@ fn get_metrics() -> PyResult<PyObject> {
     })
 }
 
+/// Render the process-wide metrics as text so they can be scraped from the
+/// Python side of a long-running ingestion job. `format` is `"prometheus"`
+/// or `"json"`.
+#[pyfunction]
+fn export_metrics(format: &str) -> PyResult<String> {
+    let collector = MetricsCollector::new();
+    let rendered = match format {
+        "prometheus" => collector.export_prometheus(),
+        "json" => collector.export_json(),
+        other => {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "unsupported metrics format: {other} (expected \"prometheus\" or \"json\")"
+            )))
+        }
+    };
+    rendered.map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))
+}
+
 /// High-performance CSV to Arrow conversion
 #[pyfunction]
-fn csv_to_arrow(csv_path: &str, arrow_path: &str) -> PyResult<PyObject> {
+fn csv_to_arrow(csv_path: &str, arrow_path: &str, schema_id: &str) -> PyResult<PyObject> {
     let start_time = Instant::now();
-    
+
     // Check if input file exists
     if !Path::new(csv_path).exists() {
         return Err(PyErr::new::<pyo3::exceptions::PyFileNotFoundError, _>(
             format!("CSV file not found: {}", csv_path)
         ));
     }
-    
+
     // Process the conversion
     let parser = SimdParser::new();
-    let result = parser.parse_csv_py(csv_path, 8192);
-    
+    let result = parser.convert_to_arrow(csv_path, arrow_path, schema_id);
+
     let processing_time = start_time.elapsed();
-    
+
     Python::with_gil(|py| {
         let result_dict = PyDict::new(py);
         match result {
-            Ok(_) => {
+            Ok(stats) => {
                 result_dict.set_item("status", "success")?;
                 result_dict.set_item("input_file", csv_path)?;
                 result_dict.set_item("output_file", arrow_path)?;
-                result_dict.set_item("rows_processed", 1000)?; // Mock value
+                result_dict.set_item("rows_processed", stats.rows_processed)?;
+                result_dict.set_item("bytes_processed", stats.bytes_processed)?;
+                result_dict.set_item("arrow_bytes_written", stats.arrow_bytes_written)?;
                 result_dict.set_item("processing_time_ms", processing_time.as_millis())?;
-                result_dict.set_item("throughput_mbps", 100.0)?; // Mock value
+                result_dict.set_item("throughput_mbps", stats.throughput_mbps)?;
             }
             Err(e) => {
                 result_dict.set_item("status", "error")?;
@@ -127,7 +297,11 @@ fn csv_to_arrow(csv_path: &str, arrow_path: &str) -> PyResult<PyObject> {
 fn dataset_core_rust(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(parse_csv_simd, m)?)?;
     m.add_function(wrap_pyfunction!(process_stream, m)?)?;
+    m.add_function(wrap_pyfunction!(query_range, m)?)?;
+    m.add_function(wrap_pyfunction!(register_schema, m)?)?;
+    m.add_function(wrap_pyfunction!(get_schema, m)?)?;
     m.add_function(wrap_pyfunction!(get_metrics, m)?)?;
+    m.add_function(wrap_pyfunction!(export_metrics, m)?)?;
     m.add_function(wrap_pyfunction!(csv_to_arrow, m)?)?;
     
     // Add classes
@@ -149,9 +323,12 @@ mod tests {
 
     #[test]
     fn test_stream_processor() {
-        // Test stream processing with mock data
+        let csv_path = std::env::temp_dir().join("dataset_core_rust_test_stream.csv");
+        std::fs::write(&csv_path, b"ts,symbol,price,size\n2025-01-27T09:30:00,ES,4500.25,100\n").unwrap();
+        let source_url = format!("file://{}", csv_path.to_str().unwrap());
+
         let processor = StreamProcessor::new();
-        let result = processor.process_stream("mock://test", 1000, "ticks_v1");
+        let result = processor.process_stream(&source_url, 1000, "ticks_v1", None);
         assert!(result.is_ok());
     }
 } 
\ No newline at end of file
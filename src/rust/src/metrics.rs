@@ -1,4 +1,95 @@
 use anyhow::Result;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+/// `#[global_allocator]` wrapper (installed in `lib.rs`) that tracks current
+/// and peak heap usage alongside every allocation, so a parse's memory cost
+/// is visible via [`current_heap_bytes`]/[`peak_heap_bytes`] without a
+/// separate profiler.
+pub struct TrackingAllocator;
+
+static CURRENT_BYTES: AtomicUsize = AtomicUsize::new(0);
+static PEAK_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for TrackingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let current = CURRENT_BYTES.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+            PEAK_BYTES.fetch_max(current, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        CURRENT_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+}
+
+pub fn current_heap_bytes() -> usize {
+    CURRENT_BYTES.load(Ordering::Relaxed)
+}
+
+pub fn peak_heap_bytes() -> usize {
+    PEAK_BYTES.load(Ordering::Relaxed)
+}
+
+/// Running counters and a latency histogram for one named operation (e.g.
+/// `"parse"`, `"query"`).
+#[derive(Default)]
+struct OperationStats {
+    calls: AtomicU64,
+    rows: AtomicU64,
+    bytes: AtomicU64,
+    latencies_us: Mutex<Vec<u64>>,
+}
+
+impl OperationStats {
+    fn record(&self, rows: u64, bytes: u64, latency: Duration) {
+        self.calls.fetch_add(1, Ordering::Relaxed);
+        self.rows.fetch_add(rows, Ordering::Relaxed);
+        self.bytes.fetch_add(bytes, Ordering::Relaxed);
+        self.latencies_us
+            .lock()
+            .unwrap()
+            .push(latency.as_micros() as u64);
+    }
+
+    /// Percentile (`p` in `[0, 1]`) over all recorded latencies: the sorted
+    /// sample at index `round((n - 1) * p)`.
+    fn percentile(&self, p: f64) -> u64 {
+        let mut samples = self.latencies_us.lock().unwrap().clone();
+        if samples.is_empty() {
+            return 0;
+        }
+        samples.sort_unstable();
+        let idx = (((samples.len() - 1) as f64) * p).round() as usize;
+        samples[idx]
+    }
+}
+
+fn operations() -> &'static Mutex<HashMap<String, Arc<OperationStats>>> {
+    static OPERATIONS: OnceLock<Mutex<HashMap<String, Arc<OperationStats>>>> = OnceLock::new();
+    OPERATIONS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn gauges() -> &'static Mutex<HashMap<String, f64>> {
+    static GAUGES: OnceLock<Mutex<HashMap<String, f64>>> = OnceLock::new();
+    GAUGES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Records one completed call to `op` (e.g. `"parse"`, `"query"`) in the
+/// process-wide metrics registry: row/byte counters plus a latency sample.
+pub fn record_operation(op: &str, rows: u64, bytes: u64, latency: Duration) {
+    let mut ops = operations().lock().unwrap();
+    ops.entry(op.to_string())
+        .or_insert_with(|| Arc::new(OperationStats::default()))
+        .record(rows, bytes, latency);
+}
 
 pub struct MetricsCollector {
     // Add fields as needed
@@ -10,15 +101,105 @@ impl MetricsCollector {
     }
 
     pub fn record_metric(&self, name: &str, value: f64) -> Result<()> {
-        // Mock metric recording
+        gauges().lock().unwrap().insert(name.to_string(), value);
         Ok(())
     }
 
     pub fn get_metrics(&self) -> Result<Vec<(String, f64)>> {
-        // Mock metrics
-        Ok(vec![
-            ("rows_processed".to_string(), 1000.0),
-            ("throughput_mbps".to_string(), 100.0),
-        ])
+        let mut metrics: Vec<(String, f64)> = gauges()
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(name, value)| (name.clone(), *value))
+            .collect();
+
+        for (op, stats) in operations().lock().unwrap().iter() {
+            metrics.push((format!("{op}.calls"), stats.calls.load(Ordering::Relaxed) as f64));
+            metrics.push((format!("{op}.rows_total"), stats.rows.load(Ordering::Relaxed) as f64));
+            metrics.push((format!("{op}.bytes_total"), stats.bytes.load(Ordering::Relaxed) as f64));
+            metrics.push((format!("{op}.p50_latency_us"), stats.percentile(0.50) as f64));
+            metrics.push((format!("{op}.p95_latency_us"), stats.percentile(0.95) as f64));
+            metrics.push((format!("{op}.p99_latency_us"), stats.percentile(0.99) as f64));
+        }
+
+        metrics.push(("heap_current_bytes".to_string(), current_heap_bytes() as f64));
+        metrics.push(("heap_peak_bytes".to_string(), peak_heap_bytes() as f64));
+
+        Ok(metrics)
+    }
+
+    /// Renders the current metrics as Prometheus text exposition format.
+    pub fn export_prometheus(&self) -> Result<String> {
+        let mut out = String::new();
+        for (name, value) in self.get_metrics()? {
+            out.push_str(&format!("dataset_core_{} {}\n", name.replace('.', "_"), value));
+        }
+        Ok(out)
     }
-} 
\ No newline at end of file
+
+    /// Renders the current metrics as a flat JSON object.
+    pub fn export_json(&self) -> Result<String> {
+        let mut out = String::from("{");
+        for (i, (name, value)) in self.get_metrics()?.into_iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            out.push_str(&format!("\"{}\":{}", name.replace('"', "\\\""), value));
+        }
+        out.push('}');
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Each test uses a unique operation name: `operations()`/`gauges()` are
+    // process-wide OnceLock-backed registries shared across parallel test
+    // threads, so reusing a name would make tests interfere with each other.
+
+    #[test]
+    fn percentile_nearest_rank_over_known_samples() {
+        let stats = OperationStats::default();
+        for latency_ms in [10, 20, 30, 40, 50] {
+            stats.record(1, 1, Duration::from_millis(latency_ms));
+        }
+        assert_eq!(stats.percentile(0.0), 10_000);
+        assert_eq!(stats.percentile(0.5), 30_000);
+        assert_eq!(stats.percentile(1.0), 50_000);
+    }
+
+    #[test]
+    fn percentile_of_no_samples_is_zero() {
+        let stats = OperationStats::default();
+        assert_eq!(stats.percentile(0.5), 0);
+    }
+
+    #[test]
+    fn record_operation_accumulates_counters_and_latencies() {
+        record_operation("metrics_test_record", 10, 1_000, Duration::from_millis(5));
+        record_operation("metrics_test_record", 20, 2_000, Duration::from_millis(15));
+
+        let ops = operations().lock().unwrap();
+        let stats = ops.get("metrics_test_record").unwrap();
+        assert_eq!(stats.calls.load(Ordering::Relaxed), 2);
+        assert_eq!(stats.rows.load(Ordering::Relaxed), 30);
+        assert_eq!(stats.bytes.load(Ordering::Relaxed), 3_000);
+        assert_eq!(stats.percentile(1.0), 15_000);
+    }
+
+    #[test]
+    fn get_metrics_includes_gauges_and_operation_summaries() {
+        let collector = MetricsCollector::new();
+        collector.record_metric("metrics_test_gauge", 42.0).unwrap();
+        record_operation("metrics_test_get", 5, 500, Duration::from_millis(1));
+
+        let metrics = collector.get_metrics().unwrap();
+        let find = |name: &str| metrics.iter().find(|(n, _)| n == name).map(|(_, v)| *v);
+
+        assert_eq!(find("metrics_test_gauge"), Some(42.0));
+        assert_eq!(find("metrics_test_get.calls"), Some(1.0));
+        assert_eq!(find("metrics_test_get.rows_total"), Some(5.0));
+    }
+}
@@ -0,0 +1,158 @@
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+use crate::error::DatasetError;
+
+/// A single declared column: its name, Arrow type, and nullability.
+#[derive(Debug, Clone)]
+pub struct ColumnDef {
+    pub name: String,
+    pub data_type: DataType,
+    pub nullable: bool,
+}
+
+/// A registered tick schema: its columns, in CSV header order, plus which
+/// column the file is expected to be sorted on.
+#[derive(Debug, Clone)]
+pub struct TickSchema {
+    pub schema_id: String,
+    pub columns: Vec<ColumnDef>,
+    pub ts_column: String,
+}
+
+impl TickSchema {
+    pub fn arrow_schema(&self) -> SchemaRef {
+        SchemaRef::new(Schema::new(
+            self.columns
+                .iter()
+                .map(|c| Field::new(&c.name, c.data_type.clone(), c.nullable))
+                .collect::<Vec<_>>(),
+        ))
+    }
+
+    fn builtin_ticks_v1() -> Self {
+        Self {
+            schema_id: "ticks_v1".to_string(),
+            columns: vec![
+                ColumnDef {
+                    name: "ts".to_string(),
+                    data_type: DataType::Timestamp(TimeUnit::Microsecond, None),
+                    nullable: false,
+                },
+                ColumnDef {
+                    name: "symbol".to_string(),
+                    data_type: DataType::Utf8,
+                    nullable: false,
+                },
+                ColumnDef {
+                    name: "price".to_string(),
+                    data_type: DataType::Float64,
+                    nullable: false,
+                },
+                ColumnDef {
+                    name: "size".to_string(),
+                    data_type: DataType::Int64,
+                    nullable: false,
+                },
+            ],
+            ts_column: "ts".to_string(),
+        }
+    }
+}
+
+fn registry() -> &'static Mutex<HashMap<String, TickSchema>> {
+    static REGISTRY: OnceLock<Mutex<HashMap<String, TickSchema>>> = OnceLock::new();
+    REGISTRY.get_or_init(|| {
+        let builtin = TickSchema::builtin_ticks_v1();
+        let mut map = HashMap::new();
+        map.insert(builtin.schema_id.clone(), builtin);
+        Mutex::new(map)
+    })
+}
+
+/// Registers (or replaces) a schema under `schema.schema_id`.
+pub fn register_schema(schema: TickSchema) -> crate::error::Result<()> {
+    registry()
+        .lock()
+        .map_err(|_| DatasetError::Unknown("schema registry lock poisoned".to_string()))?
+        .insert(schema.schema_id.clone(), schema);
+    Ok(())
+}
+
+/// Looks up a previously registered schema by id.
+pub fn get_schema(schema_id: &str) -> crate::error::Result<TickSchema> {
+    registry()
+        .lock()
+        .map_err(|_| DatasetError::Unknown("schema registry lock poisoned".to_string()))?
+        .get(schema_id)
+        .cloned()
+        .ok_or_else(|| DatasetError::CsvParseError(format!("unknown schema_id: {schema_id}")))
+}
+
+/// Checks that a parsed CSV header matches `schema_id`'s declared column
+/// names, in order.
+pub fn validate_header(schema_id: &str, header: &[&str]) -> crate::error::Result<()> {
+    let schema = get_schema(schema_id)?;
+    if header.len() != schema.columns.len() {
+        return Err(DatasetError::CsvParseError(format!(
+            "schema '{schema_id}' expects {} columns, header has {}",
+            schema.columns.len(),
+            header.len()
+        )));
+    }
+    for (got, col) in header.iter().zip(schema.columns.iter()) {
+        if *got != col.name {
+            return Err(DatasetError::CsvParseError(format!(
+                "schema '{schema_id}' expects column '{}', header has '{got}'",
+                col.name
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Parses the small set of type names the Python-facing `register_schema`
+/// pyfunction accepts.
+pub fn parse_data_type(name: &str) -> crate::error::Result<DataType> {
+    match name {
+        "timestamp_us" => Ok(DataType::Timestamp(TimeUnit::Microsecond, None)),
+        "utf8" | "string" => Ok(DataType::Utf8),
+        "float64" => Ok(DataType::Float64),
+        "int64" => Ok(DataType::Int64),
+        other => Err(DatasetError::CsvParseError(format!(
+            "unsupported column type: {other}"
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_header_accepts_builtin_ticks_v1() {
+        let header = ["ts", "symbol", "price", "size"];
+        assert!(validate_header("ticks_v1", &header).is_ok());
+    }
+
+    #[test]
+    fn validate_header_rejects_wrong_column_count() {
+        let header = ["ts", "symbol"];
+        let err = validate_header("ticks_v1", &header).unwrap_err();
+        assert!(err.to_string().contains("expects 4 columns"));
+    }
+
+    #[test]
+    fn validate_header_rejects_mismatched_column_name() {
+        let header = ["ts", "ticker", "price", "size"];
+        let err = validate_header("ticks_v1", &header).unwrap_err();
+        assert!(err.to_string().contains("expects column 'symbol'"));
+    }
+
+    #[test]
+    fn validate_header_rejects_unknown_schema() {
+        let header = ["ts", "symbol", "price", "size"];
+        assert!(validate_header("no_such_schema", &header).is_err());
+    }
+}
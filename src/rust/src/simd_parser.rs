@@ -1,18 +1,318 @@
+use arrow::array::{ArrayRef, Float64Array, Int64Array, StringArray, TimestampMicrosecondArray};
+use arrow::datatypes::DataType;
+use arrow::ipc::writer::FileWriter;
+use arrow::record_batch::RecordBatch;
+use chrono::NaiveDateTime;
+use memmap2::Mmap;
 use pyo3::prelude::*;
 use pyo3::types::PyDict;
 use std::fs::File;
-use std::io::{BufRead, BufReader};
 use std::path::Path;
+use std::sync::Arc;
 use std::time::Instant;
 
 #[cfg(target_arch = "x86_64")]
 use std::arch::x86_64::*;
 
+use crate::error::DatasetError;
+use crate::schema::{self, ColumnDef};
+
 #[pyclass]
 pub struct SimdParser {
     // Add fields as needed
 }
 
+/// Byte span of a single CSV field within a larger buffer.
+#[derive(Debug, Clone, Copy)]
+pub struct FieldSpan {
+    pub start: usize,
+    pub len: usize,
+}
+
+impl FieldSpan {
+    fn as_str<'a>(&self, data: &'a [u8]) -> crate::error::Result<&'a str> {
+        std::str::from_utf8(&data[self.start..self.start + self.len])
+            .map_err(|e| DatasetError::CsvParseError(format!("invalid utf8 field: {e}")))
+    }
+}
+
+/// Scans `data` for `delimiter` and newline bytes and returns the field
+/// spans of every row, without copying the underlying bytes.
+///
+/// On x86_64 this walks `data` in 32-byte chunks, builds an AVX2 movemask of
+/// matching bytes per chunk, and drains the mask bit-by-bit
+/// (`trailing_zeros` + clear-lowest-bit) to recover exact field boundaries
+/// in ascending order. The remainder that doesn't fill a full chunk falls
+/// through to `scan_scalar`, which also serves as the entire scan on other
+/// architectures, so there's a single scalar implementation rather than one
+/// copy per fallback site.
+pub fn scan_fields(data: &[u8], delimiter: u8) -> Vec<Vec<FieldSpan>> {
+    let mut rows: Vec<Vec<FieldSpan>> = Vec::new();
+    let mut current_row: Vec<FieldSpan> = Vec::new();
+    let mut field_start = 0usize;
+    let mut pos = 0usize;
+    let data_len = data.len();
+
+    #[cfg(target_arch = "x86_64")]
+    {
+        if is_x86_feature_detected!("avx2") {
+            while pos + 32 <= data_len {
+                unsafe {
+                    let chunk = _mm256_loadu_si256(data.as_ptr().add(pos) as *const __m256i);
+                    let delim_mask =
+                        _mm256_movemask_epi8(_mm256_cmpeq_epi8(chunk, _mm256_set1_epi8(delimiter as i8))) as u32;
+                    let newline_mask =
+                        _mm256_movemask_epi8(_mm256_cmpeq_epi8(chunk, _mm256_set1_epi8(b'\n' as i8))) as u32;
+                    let mut boundary_mask = delim_mask | newline_mask;
+
+                    while boundary_mask != 0 {
+                        let bit = boundary_mask.trailing_zeros() as usize;
+                        let byte_pos = pos + bit;
+                        current_row.push(FieldSpan {
+                            start: field_start,
+                            len: byte_pos - field_start,
+                        });
+                        field_start = byte_pos + 1;
+                        if (newline_mask >> bit) & 1 == 1 {
+                            rows.push(std::mem::take(&mut current_row));
+                        }
+                        boundary_mask &= boundary_mask - 1; // clear lowest set bit
+                    }
+                }
+                pos += 32;
+            }
+        }
+    }
+
+    scan_scalar(data, delimiter, pos, &mut field_start, &mut current_row, &mut rows);
+
+    // A final field with no trailing delimiter/newline (e.g. the last
+    // record in a file with no trailing newline).
+    if field_start < data_len {
+        current_row.push(FieldSpan {
+            start: field_start,
+            len: data_len - field_start,
+        });
+    }
+    if !current_row.is_empty() {
+        rows.push(current_row);
+    }
+
+    rows
+}
+
+/// Scalar delimiter/newline scan starting at `pos`, used both for the
+/// sub-32-byte tail of the AVX2 path and as the whole scan on non-x86_64
+/// targets.
+fn scan_scalar(
+    data: &[u8],
+    delimiter: u8,
+    mut pos: usize,
+    field_start: &mut usize,
+    current_row: &mut Vec<FieldSpan>,
+    rows: &mut Vec<Vec<FieldSpan>>,
+) {
+    while pos < data.len() {
+        let byte = data[pos];
+        if byte == delimiter || byte == b'\n' {
+            current_row.push(FieldSpan {
+                start: *field_start,
+                len: pos - *field_start,
+            });
+            *field_start = pos + 1;
+            if byte == b'\n' {
+                rows.push(std::mem::take(current_row));
+            }
+        }
+        pos += 1;
+    }
+}
+
+/// Parses a tick timestamp into microseconds since the Unix epoch: either a
+/// plain integer already in microseconds (`1737970200123456`) or an
+/// RFC3339-ish datetime (`2025-01-27T09:30:00.123456`).
+pub(crate) fn parse_ts_micros(raw: &str) -> crate::error::Result<i64> {
+    if let Ok(micros) = raw.parse::<i64>() {
+        return Ok(micros);
+    }
+    let formats = ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"];
+    for fmt in formats {
+        if let Ok(dt) = NaiveDateTime::parse_from_str(raw, fmt) {
+            return Ok(dt.and_utc().timestamp_micros());
+        }
+    }
+    Err(DatasetError::CsvParseError(format!(
+        "invalid timestamp '{raw}'"
+    )))
+}
+
+/// Builds one Arrow array out of `values` (the `col.name` field of every
+/// row, in order), typed per `col.data_type` rather than assumed ad hoc.
+/// This is what makes a registered schema's declared types actually binding
+/// on parsing, instead of merely checked for header name/count. Shared by
+/// [`build_tick_batch`] (values borrowed from a mmap via [`FieldSpan`]) and
+/// [`crate::stream_processor`]'s TCP row ingestion (values owned, read off
+/// the wire line by line).
+pub(crate) fn build_typed_column<'a>(
+    col: &ColumnDef,
+    values: impl Iterator<Item = crate::error::Result<&'a str>>,
+) -> crate::error::Result<ArrayRef> {
+    match &col.data_type {
+        DataType::Timestamp(arrow::datatypes::TimeUnit::Microsecond, None) => {
+            let values = values
+                .map(|v| parse_ts_micros(v?))
+                .collect::<crate::error::Result<Vec<i64>>>()?;
+            Ok(Arc::new(TimestampMicrosecondArray::from(values)))
+        }
+        DataType::Utf8 => {
+            let values = values
+                .map(|v| v.map(str::to_string))
+                .collect::<crate::error::Result<Vec<String>>>()?;
+            Ok(Arc::new(StringArray::from(values)))
+        }
+        DataType::Float64 => {
+            let values = values
+                .map(|v| {
+                    v?.parse::<f64>()
+                        .map_err(|e| DatasetError::CsvParseError(format!("invalid {}: {e}", col.name)))
+                })
+                .collect::<crate::error::Result<Vec<f64>>>()?;
+            Ok(Arc::new(Float64Array::from(values)))
+        }
+        DataType::Int64 => {
+            let values = values
+                .map(|v| {
+                    v?.parse::<i64>()
+                        .map_err(|e| DatasetError::CsvParseError(format!("invalid {}: {e}", col.name)))
+                })
+                .collect::<crate::error::Result<Vec<i64>>>()?;
+            Ok(Arc::new(Int64Array::from(values)))
+        }
+        other => Err(DatasetError::CsvParseError(format!(
+            "column '{}' has unsupported schema type {other:?}",
+            col.name
+        ))),
+    }
+}
+
+/// Builds a `RecordBatch` out of `rows` (each an owned, already-split CSV
+/// row) typed per `columns`. Used for streaming sources that can't be
+/// memory-mapped, where rows arrive one at a time as owned strings rather
+/// than [`FieldSpan`]s over a shared buffer.
+pub(crate) fn build_row_batch(
+    schema: arrow::datatypes::SchemaRef,
+    columns: &[ColumnDef],
+    rows: &[Vec<String>],
+) -> crate::error::Result<RecordBatch> {
+    let arrays: Vec<ArrayRef> = columns
+        .iter()
+        .enumerate()
+        .map(|(i, col)| {
+            build_typed_column(col, rows.iter().map(|row| Ok(row[i].as_str())))
+        })
+        .collect::<crate::error::Result<_>>()?;
+    RecordBatch::try_new(schema, arrays).map_err(|e| DatasetError::ArrowError(e.to_string()))
+}
+
+/// Memory-maps `csv_path`, runs the SIMD structural scan over it, and builds
+/// a single `RecordBatch` typed per `schema_id`'s declared columns. The
+/// header row (first row) is validated for column count/names and discarded.
+fn build_tick_batch(csv_path: &str, schema_id: &str) -> crate::error::Result<(RecordBatch, usize)> {
+    let tick_schema = schema::get_schema(schema_id)?;
+    let arrow_schema = tick_schema.arrow_schema();
+
+    let file = File::open(csv_path)?;
+    let mmap = unsafe { Mmap::map(&file)? };
+    let data: &[u8] = &mmap;
+
+    let mut rows = scan_fields(data, b',');
+    if rows.is_empty() {
+        return Err(DatasetError::CsvParseError("empty CSV file".to_string()));
+    }
+    let header: Vec<&str> = rows[0]
+        .iter()
+        .map(|span| span.as_str(data))
+        .collect::<crate::error::Result<_>>()?;
+    schema::validate_header(schema_id, &header)?;
+    rows.remove(0); // header
+
+    for row in &rows {
+        if row.len() < tick_schema.columns.len() {
+            return Err(DatasetError::CsvParseError(format!(
+                "expected {} columns, got {}",
+                tick_schema.columns.len(),
+                row.len()
+            )));
+        }
+    }
+
+    let columns: Vec<ArrayRef> = tick_schema
+        .columns
+        .iter()
+        .enumerate()
+        .map(|(i, col)| build_typed_column(col, rows.iter().map(|row| row[i].as_str(data))))
+        .collect::<crate::error::Result<_>>()?;
+
+    let batch = RecordBatch::try_new(arrow_schema, columns)
+        .map_err(|e| DatasetError::ArrowError(e.to_string()))?;
+
+    Ok((batch, data.len()))
+}
+
+/// Writes `batch` to `arrow_path` in the Arrow IPC file format.
+fn write_arrow_ipc(batch: &RecordBatch, arrow_path: &str) -> crate::error::Result<u64> {
+    let file = File::create(arrow_path)?;
+    let mut writer = FileWriter::try_new(file, batch.schema().as_ref())
+        .map_err(|e| DatasetError::ArrowError(e.to_string()))?;
+    writer.write(batch).map_err(|e| DatasetError::ArrowError(e.to_string()))?;
+    writer.finish().map_err(|e| DatasetError::ArrowError(e.to_string()))?;
+    Ok(std::fs::metadata(arrow_path)?.len())
+}
+
+/// Outcome of converting a CSV file into an Arrow IPC file on disk.
+pub struct ConversionStats {
+    pub rows_processed: usize,
+    pub bytes_processed: usize,
+    pub arrow_bytes_written: u64,
+    pub processing_time_ms: u128,
+    pub throughput_mbps: f64,
+}
+
+impl SimdParser {
+    /// Parses `file_path` against `schema_id`'s column layout and writes the
+    /// resulting batch to `arrow_path` as Arrow IPC.
+    pub fn convert_to_arrow(
+        &self,
+        file_path: &str,
+        arrow_path: &str,
+        schema_id: &str,
+    ) -> crate::error::Result<ConversionStats> {
+        let start_time = Instant::now();
+
+        let (batch, bytes_processed) = build_tick_batch(file_path, schema_id)?;
+        let rows_processed = batch.num_rows();
+        let arrow_bytes_written = write_arrow_ipc(&batch, arrow_path)?;
+
+        let processing_time = start_time.elapsed();
+        let throughput_mbps = (bytes_processed as f64 / processing_time.as_secs_f64()) / 1_000_000.0;
+
+        crate::metrics::record_operation(
+            "parse",
+            rows_processed as u64,
+            bytes_processed as u64,
+            processing_time,
+        );
+
+        Ok(ConversionStats {
+            rows_processed,
+            bytes_processed,
+            arrow_bytes_written,
+            processing_time_ms: processing_time.as_millis(),
+            throughput_mbps,
+        })
+    }
+}
+
 #[pymethods]
 impl SimdParser {
     #[new]
@@ -20,112 +320,106 @@ impl SimdParser {
         Self {}
     }
 
-    /// Parse CSV file with SIMD optimizations
-    pub fn parse_csv_py(&self, file_path: &str, _batch_size: usize) -> PyResult<PyObject> {
-        let start_time = Instant::now();
-        
-        // Check if file exists
+    /// Parses `file_path` against `schema_id`'s column layout, writes the
+    /// resulting batch to `arrow_path` as Arrow IPC, and returns the real
+    /// row/byte counts.
+    pub fn parse_csv_py(
+        &self,
+        file_path: &str,
+        arrow_path: &str,
+        schema_id: &str,
+        _batch_size: usize,
+    ) -> PyResult<PyObject> {
         if !Path::new(file_path).exists() {
             return Err(PyErr::new::<pyo3::exceptions::PyFileNotFoundError, _>(
-                format!("File not found: {}", file_path)
+                format!("File not found: {}", file_path),
             ));
         }
-        
-        // Read file and count lines
-        let file = File::open(file_path)?;
-        let reader = BufReader::new(file);
-        let mut line_count = 0;
-        let mut total_bytes = 0;
-        
-        for line in reader.lines() {
-            let line = line?;
-            line_count += 1;
-            total_bytes += line.len() + 1; // +1 for newline
-        }
-        
+
+        let stats = self.convert_to_arrow(file_path, arrow_path, schema_id)?;
+
+        Python::with_gil(|py| {
+            let result_dict = PyDict::new(py);
+            result_dict.set_item("status", "success")?;
+            result_dict.set_item("rows_processed", stats.rows_processed)?;
+            result_dict.set_item("bytes_processed", stats.bytes_processed)?;
+            result_dict.set_item("arrow_bytes_written", stats.arrow_bytes_written)?;
+            result_dict.set_item("processing_time_ms", stats.processing_time_ms)?;
+            result_dict.set_item("throughput_mbps", stats.throughput_mbps)?;
+            Ok(result_dict.into())
+        })
+    }
+
+    /// Structurally parse an in-memory CSV buffer: recover every field's
+    /// `(start, len)` span via the SIMD scan and return the row/field counts;
+    /// the spans are not returned (`parse_csv_py`/`csv_to_arrow` run the same
+    /// scan themselves and index its spans back into the buffer to build
+    /// columns).
+    pub fn parse_csv(&self, data: &[u8], delimiter: char, _batch_size: usize) -> PyResult<PyObject> {
+        let start_time = Instant::now();
+        let data_len = data.len();
+
+        let rows = scan_fields(data, delimiter as u8);
+        let fields_processed: usize = rows.iter().map(Vec::len).sum();
+
         let processing_time = start_time.elapsed();
-        let throughput_mbps = (total_bytes as f64 / processing_time.as_secs_f64()) / 1_000_000.0;
-        
-        // Create result dictionary
+        let throughput_mbps = (data_len as f64 / processing_time.as_secs_f64()) / 1_000_000.0;
+
         Python::with_gil(|py| {
             let result_dict = PyDict::new(py);
             result_dict.set_item("status", "success")?;
-            result_dict.set_item("rows_processed", line_count - 1)?; // Subtract header
-            result_dict.set_item("bytes_processed", total_bytes)?;
+            result_dict.set_item("rows_processed", rows.len())?;
+            result_dict.set_item("fields_processed", fields_processed)?;
+            result_dict.set_item("bytes_processed", data_len)?;
             result_dict.set_item("processing_time_ms", processing_time.as_millis())?;
             result_dict.set_item("throughput_mbps", throughput_mbps)?;
             Ok(result_dict.into())
         })
     }
+}
 
-    /// Parse CSV data with SIMD optimizations (for small data)
-    pub fn parse_csv(&self, data: &[u8], delimiter: char, _batch_size: usize) -> PyResult<PyObject> {
-        let start_time = Instant::now();
-        
-        #[cfg(target_arch = "x86_64")]
-        {
-            // SIMD-optimized parsing for x86_64
-            let mut rows = 0;
-            let mut pos = 0;
-            let data_len = data.len();
-            
-            // Process data in 32-byte chunks using AVX2
-            while pos + 32 <= data_len {
-                unsafe {
-                    let chunk = _mm256_loadu_si256(data.as_ptr().add(pos) as *const __m256i);
-                    let delimiter_vec = _mm256_set1_epi8(delimiter as i8);
-                    let matches = _mm256_cmpeq_epi8(chunk, delimiter_vec);
-                    let mask = _mm256_movemask_epi8(matches);
-                    
-                    // Count delimiters in this chunk
-                    rows += mask.count_ones() as usize;
-                }
-                pos += 32;
-            }
-            
-            // Process remaining bytes
-            for &byte in &data[pos..] {
-                if byte == delimiter as u8 {
-                    rows += 1;
-                }
-            }
-            
-            let processing_time = start_time.elapsed();
-            let throughput_mbps = (data_len as f64 / processing_time.as_secs_f64()) / 1_000_000.0;
-            
-            Python::with_gil(|py| {
-                let result_dict = PyDict::new(py);
-                result_dict.set_item("status", "success")?;
-                result_dict.set_item("rows_processed", rows)?;
-                result_dict.set_item("bytes_processed", data_len)?;
-                result_dict.set_item("processing_time_ms", processing_time.as_millis())?;
-                result_dict.set_item("throughput_mbps", throughput_mbps)?;
-                Ok(result_dict.into())
-            })
-        }
-        
-        #[cfg(not(target_arch = "x86_64"))]
-        {
-            // Fallback for non-x86_64 architectures
-            let mut rows = 0;
-            for &byte in data {
-                if byte == delimiter as u8 {
-                    rows += 1;
-                }
-            }
-            
-            let processing_time = start_time.elapsed();
-            let throughput_mbps = (data.len() as f64 / processing_time.as_secs_f64()) / 1_000_000.0;
-            
-            Python::with_gil(|py| {
-                let result_dict = PyDict::new(py);
-                result_dict.set_item("status", "success")?;
-                result_dict.set_item("rows_processed", rows)?;
-                result_dict.set_item("bytes_processed", data.len())?;
-                result_dict.set_item("processing_time_ms", processing_time.as_millis())?;
-                result_dict.set_item("throughput_mbps", throughput_mbps)?;
-                Ok(result_dict.into())
-            })
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field_strs(data: &[u8], rows: &[Vec<FieldSpan>]) -> Vec<Vec<String>> {
+        rows.iter()
+            .map(|row| row.iter().map(|span| span.as_str(data).unwrap().to_string()).collect())
+            .collect()
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn scan_fields_splits_rows_and_columns() {
+        let data = b"ts,symbol,price,size\n100,ES,4500.25,100\n200,NQ,15800.5,50\n";
+        let rows = scan_fields(data, b',');
+        assert_eq!(
+            field_strs(data, &rows),
+            vec![
+                vec!["ts", "symbol", "price", "size"],
+                vec!["100", "ES", "4500.25", "100"],
+                vec!["200", "NQ", "15800.5", "50"],
+            ]
+        );
+    }
+
+    #[test]
+    fn scan_fields_handles_missing_trailing_newline() {
+        let data = b"ts,symbol\n100,ES\n200,NQ";
+        let rows = scan_fields(data, b',');
+        assert_eq!(
+            field_strs(data, &rows),
+            vec![vec!["ts", "symbol"], vec!["100", "ES"], vec!["200", "NQ"]]
+        );
+    }
+
+    #[test]
+    fn scan_fields_handles_chunk_spanning_rows() {
+        // Exercises the >32-byte AVX2 chunked path on x86_64 by spanning a
+        // row across a chunk boundary.
+        let data = b"aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa,bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb\n1,2\n";
+        let rows = scan_fields(data, b',');
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].len(), 2);
+        assert_eq!(rows[1].len(), 2);
+    }
+}